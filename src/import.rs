@@ -0,0 +1,292 @@
+use crate::account::Account;
+use crate::split::Split;
+use crate::transaction::Transaction;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::io::Read;
+use std::str::FromStr;
+
+/// Maps CSV header names to the semantic fields of a `Transaction`.
+///
+/// `date` and `amount` are mandatory; everything else is optional and left
+/// blank on the resulting `Transaction` when not supplied.
+#[derive(Default, Debug, Clone)]
+pub struct ColumnMapping {
+    date: String,
+    amount: String,
+    payee: Option<String>,
+    memo: Option<String>,
+    category: Option<String>,
+    cleared_status: Option<String>,
+    /// Column shared by all rows that belong to the same transaction;
+    /// consecutive rows sharing a value here are collapsed into one
+    /// `Transaction` with a `Split` per row. Rows with the same value that
+    /// are not adjacent become separate transactions.
+    split_header: Option<String>,
+}
+
+impl ColumnMapping {
+    pub fn new(date: &str, amount: &str) -> Self {
+        ColumnMapping {
+            date: String::from(date),
+            amount: String::from(amount),
+            ..Default::default()
+        }
+    }
+
+    pub fn payee(mut self, val: &str) -> Self {
+        self.payee = Some(String::from(val));
+        self
+    }
+
+    pub fn memo(mut self, val: &str) -> Self {
+        self.memo = Some(String::from(val));
+        self
+    }
+
+    pub fn category(mut self, val: &str) -> Self {
+        self.category = Some(String::from(val));
+        self
+    }
+
+    pub fn cleared_status(mut self, val: &str) -> Self {
+        self.cleared_status = Some(String::from(val));
+        self
+    }
+
+    pub fn split_header(mut self, val: &str) -> Self {
+        self.split_header = Some(String::from(val));
+        self
+    }
+}
+
+/// Settings controlling how a CSV file is turned into `Transaction`s.
+#[derive(Debug, Clone)]
+pub struct ImportConfig {
+    mapping: ColumnMapping,
+    /// `chrono` format string used to parse the date column; defaults to
+    /// `%Y-%m-%d` when not set.
+    date_format: Option<String>,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+}
+
+impl ImportConfig {
+    pub fn new(mapping: ColumnMapping) -> Self {
+        ImportConfig {
+            mapping,
+            date_format: None,
+            start: None,
+            end: None,
+        }
+    }
+
+    pub fn date_format(mut self, val: &str) -> Self {
+        self.date_format = Some(String::from(val));
+        self
+    }
+
+    pub fn start(mut self, val: DateTime<Utc>) -> Self {
+        self.start = Some(val);
+        self
+    }
+
+    pub fn end(mut self, val: DateTime<Utc>) -> Self {
+        self.end = Some(val);
+        self
+    }
+}
+
+/// Reads CSV data and turns each (group of) row(s) into a `Transaction`
+/// owned by `account`, applying `config`'s column mapping, date format and
+/// date-range filter.
+///
+/// Rows whose date column fails to parse, or whose amount column isn't a
+/// valid number, are reported as a descriptive `Err` rather than causing a
+/// panic.
+pub fn import_csv<'a, R: Read>(
+    reader: R,
+    account: &'a Account,
+    config: &ImportConfig,
+) -> Result<Vec<Transaction<'a>>, String> {
+    let mut csv_reader = csv::Reader::from_reader(reader);
+    let headers = csv_reader
+        .headers()
+        .map_err(|e| format!("Failed to read CSV header: {}", e))?
+        .clone();
+
+    let column = |name: &str| -> Result<usize, String> {
+        headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| format!("CSV is missing expected column `{}`", name))
+    };
+
+    let date_idx = column(&config.mapping.date)?;
+    let amount_idx = column(&config.mapping.amount)?;
+    let payee_idx = config.mapping.payee.as_deref().map(column).transpose()?;
+    let memo_idx = config.mapping.memo.as_deref().map(column).transpose()?;
+    let category_idx = config.mapping.category.as_deref().map(column).transpose()?;
+    let cleared_idx = config
+        .mapping
+        .cleared_status
+        .as_deref()
+        .map(column)
+        .transpose()?;
+    let split_idx = config.mapping.split_header.as_deref().map(column).transpose()?;
+
+    let date_format = config.date_format.as_deref().unwrap_or("%Y-%m-%d");
+
+    let mut groups: Vec<Vec<csv::StringRecord>> = Vec::new();
+    let mut last_key: Option<String> = None;
+
+    for result in csv_reader.records() {
+        let record = result.map_err(|e| format!("Failed to read CSV row: {}", e))?;
+
+        match split_idx {
+            Some(idx) if last_key.as_deref() == Some(field(&record, idx)) => {
+                groups.last_mut().unwrap().push(record);
+            }
+            Some(idx) => {
+                last_key = Some(field(&record, idx).to_string());
+                groups.push(vec![record]);
+            }
+            None => groups.push(vec![record]),
+        }
+    }
+
+    let mut transactions = Vec::new();
+
+    for rows in groups {
+        let head = &rows[0];
+
+        let date = parse_date(field(head, date_idx), date_format)?;
+        if !in_range(date, config.start, config.end) {
+            continue;
+        }
+
+        let mut t = Transaction::new(account)
+            .date(date)
+            .payee(payee_idx.map_or("", |i| field(head, i)))
+            .memo(memo_idx.map_or("", |i| field(head, i)))
+            .category(category_idx.map_or("", |i| field(head, i)))
+            .cleared_status(cleared_idx.map_or("", |i| field(head, i)));
+
+        if rows.len() > 1 {
+            for row in &rows {
+                let amount = parse_amount(field(row, amount_idx))?;
+                let split = Split::new()
+                    .category(category_idx.map_or("", |i| field(row, i)))
+                    .memo(memo_idx.map_or("", |i| field(row, i)))
+                    .amount(amount)
+                    .build();
+                t = t.with_split(&split);
+            }
+        } else {
+            let amount = parse_amount(field(head, amount_idx))?;
+            t = t.amount(amount);
+        }
+
+        transactions.push(t.build()?);
+    }
+
+    Ok(transactions)
+}
+
+fn field(record: &csv::StringRecord, idx: usize) -> &str {
+    record.get(idx).unwrap_or("")
+}
+
+fn in_range(date: DateTime<Utc>, start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> bool {
+    if let Some(start) = start {
+        if date < start {
+            return false;
+        }
+    }
+    if let Some(end) = end {
+        if date > end {
+            return false;
+        }
+    }
+    true
+}
+
+fn parse_date(s: &str, format: &str) -> Result<DateTime<Utc>, String> {
+    let date = NaiveDate::parse_from_str(s, format)
+        .map_err(|e| format!("Could not parse date `{}` with format `{}`: {}", s, format, e))?;
+    Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()))
+}
+
+fn parse_amount(s: &str) -> Result<Decimal, String> {
+    let trimmed = s.trim();
+    Decimal::from_str(trimmed).map_err(|_| format!("Could not parse amount `{}`", trimmed))
+}
+
+#[cfg(test)]
+mod import_test {
+    use super::*;
+    use crate::account::AccountType;
+    use rust_decimal_macros::dec;
+
+    fn account() -> Account {
+        Account::new().name("Checking").account_type(AccountType::Bank)
+    }
+
+    #[test]
+    fn imports_plain_rows() {
+        let csv = "Date,Amount,Payee\n2020-11-28,-10.00,Shop\n2020-11-29,50.00,Salary\n";
+        let a = account();
+        let mapping = ColumnMapping::new("Date", "Amount").payee("Payee");
+        let config = ImportConfig::new(mapping);
+
+        let transactions = import_csv(csv.as_bytes(), &a, &config).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].sum(), dec!(-10.00));
+        assert_eq!(transactions[1].sum(), dec!(50.00));
+    }
+
+    #[test]
+    fn filters_by_date_range() {
+        let csv = "Date,Amount\n2020-11-28,-10.00\n2020-12-15,50.00\n";
+        let a = account();
+        let mapping = ColumnMapping::new("Date", "Amount");
+        let config = ImportConfig::new(mapping)
+            .start(Utc.with_ymd_and_hms(2020, 12, 1, 0, 0, 0).unwrap())
+            .end(Utc.with_ymd_and_hms(2020, 12, 31, 0, 0, 0).unwrap());
+
+        let transactions = import_csv(csv.as_bytes(), &a, &config).unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].sum(), dec!(50.00));
+    }
+
+    #[test]
+    fn collapses_split_rows() {
+        let csv = "Date,Amount,Key,Category\n\
+                    2020-11-28,-10.00,tx1,Cat1\n\
+                    2020-11-28,-20.00,tx1,Cat2\n\
+                    2020-11-29,5.00,tx2,Cat3\n";
+        let a = account();
+        let mapping = ColumnMapping::new("Date", "Amount")
+            .category("Category")
+            .split_header("Key");
+        let config = ImportConfig::new(mapping);
+
+        let transactions = import_csv(csv.as_bytes(), &a, &config).unwrap();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0].sum(), dec!(-30.00));
+        assert_eq!(transactions[1].sum(), dec!(5.00));
+    }
+
+    #[test]
+    fn rejects_unparseable_amount() {
+        let csv = "Date,Amount\n2020-11-28,not-a-number\n";
+        let a = account();
+        let mapping = ColumnMapping::new("Date", "Amount");
+        let config = ImportConfig::new(mapping);
+
+        assert!(import_csv(csv.as_bytes(), &a, &config).is_err());
+    }
+}
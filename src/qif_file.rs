@@ -0,0 +1,300 @@
+use crate::account::Account;
+use crate::transaction::Transaction;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::fmt;
+
+/// An expected running balance for one account at a point in time, checked
+/// by `build()` against the chronological sum of that account's
+/// transactions.
+#[derive(Debug, Clone, Copy)]
+struct BalanceAssertion<'a> {
+    account: &'a Account,
+    date: DateTime<Utc>,
+    expected: Decimal,
+}
+
+/// A full QIF document made of one or more accounts, each followed by the
+/// stream of transactions that belong to it.
+///
+/// Writing `Transaction`s one at a time would repeat the `!Type:` header
+/// for every record, which Quicken doesn't accept. `QifFile` instead writes
+/// transactions in the order they were added and emits an
+/// `!Account`/`!Type:` section header only when a transaction's owning
+/// `Account` differs from the previous one's. Transactions are not
+/// reordered, so an account whose transactions are interleaved with another
+/// account's gets one section per consecutive run.
+#[derive(Default, Debug)]
+pub struct QifFile<'a> {
+    transactions: Vec<Transaction<'a>>,
+    assertions: Vec<BalanceAssertion<'a>>,
+}
+
+impl<'a> QifFile<'a> {
+    pub fn new() -> Self {
+        QifFile::default()
+    }
+
+    pub fn with_transaction(mut self, val: Transaction<'a>) -> Self {
+        self.transactions.push(val);
+        self
+    }
+
+    pub fn transactions(mut self, val: Vec<Transaction<'a>>) -> Self {
+        self.transactions = val;
+        self
+    }
+
+    /// Records that the running balance of `account`'s transactions dated
+    /// on or before `date` must equal `expected`; verified by `build()`.
+    pub fn assert_balance(mut self, account: &'a Account, date: DateTime<Utc>, expected: Decimal) -> Self {
+        self.assertions.push(BalanceAssertion {
+            account,
+            date,
+            expected,
+        });
+        self
+    }
+
+    /// Verifies every balance assertion against its account's running
+    /// total, accumulated in chronological order. Returns an `Err`
+    /// describing the offending date and the computed-vs-expected
+    /// difference on the first mismatch, so a bad register can be caught
+    /// before it's imported into Quicken.
+    pub fn build(self) -> Result<QifFile<'a>, String> {
+        let mut ordered: Vec<&Transaction<'a>> = self.transactions.iter().collect();
+        ordered.sort_by_key(|t| t.get_date());
+
+        for assertion in &self.assertions {
+            let running = ordered
+                .iter()
+                .filter(|t| std::ptr::eq(t.account(), assertion.account) && t.get_date() <= assertion.date)
+                .fold(Decimal::ZERO, |acc, t| acc + t.sum());
+
+            if running != assertion.expected {
+                return Err(format!(
+                    "Balance assertion at {} failed: expected {}, computed {} (difference {})",
+                    assertion.date.format("%m/%d/%Y"),
+                    assertion.expected,
+                    running,
+                    running - assertion.expected
+                ));
+            }
+        }
+
+        Ok(QifFile {
+            transactions: self.transactions,
+            assertions: self.assertions,
+        })
+    }
+}
+
+impl<'a> fmt::Display for QifFile<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut current: Option<&Account> = None;
+
+        for t in self.transactions.iter() {
+            let account = t.account();
+            if current.is_none_or(|c| !std::ptr::eq(c, account)) {
+                write!(f, "{}", account)?;
+                writeln!(f, "!Type:{}", account.get_type())?;
+                current = Some(account);
+            }
+            write!(f, "{}", t)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod qif_file_test {
+    use super::*;
+    use crate::account::AccountType;
+    use chrono::prelude::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn single_account_file() {
+        let a = Account::new().name("Checking").account_type(AccountType::Bank);
+
+        let t1 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .payee("Shop")
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 29, 0, 0, 0).unwrap())
+            .payee("Salary")
+            .amount(dec!(50.00))
+            .build()
+            .unwrap();
+
+        let file = QifFile::new().with_transaction(t1).with_transaction(t2);
+
+        assert_eq!(
+            file.to_string(),
+            r#"!Account
+NChecking
+TBank
+^
+!Type:Bank
+D11/28/2020
+PShop
+M
+L
+C
+T-10.00
+^
+D11/29/2020
+PSalary
+M
+L
+C
+T50.00
+^
+"#
+        );
+    }
+
+    #[test]
+    fn multiple_account_sections() {
+        let checking = Account::new().name("Checking").account_type(AccountType::Bank);
+        let savings = Account::new().name("Savings").account_type(AccountType::Cash);
+
+        let t1 = Transaction::new(&checking)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&savings)
+            .date(Utc.with_ymd_and_hms(2020, 11, 29, 0, 0, 0).unwrap())
+            .amount(dec!(20.00))
+            .build()
+            .unwrap();
+
+        let file = QifFile::new().transactions(vec![t1, t2]);
+
+        assert_eq!(
+            file.to_string(),
+            r#"!Account
+NChecking
+TBank
+^
+!Type:Bank
+D11/28/2020
+P
+M
+L
+C
+T-10.00
+^
+!Account
+NSavings
+TCash
+^
+!Type:Cash
+D11/29/2020
+P
+M
+L
+C
+T20.00
+^
+"#
+        );
+    }
+
+    #[test]
+    fn balance_assertion_passes_when_running_total_matches() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t1 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 29, 0, 0, 0).unwrap())
+            .amount(dec!(50.00))
+            .build()
+            .unwrap();
+
+        let result = QifFile::new()
+            .with_transaction(t1)
+            .with_transaction(t2)
+            .assert_balance(&a, Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap(), dec!(-10.00))
+            .assert_balance(&a, Utc.with_ymd_and_hms(2020, 11, 29, 0, 0, 0).unwrap(), dec!(40.00))
+            .build();
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn balance_assertion_reports_mismatch() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t1 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+
+        let result = QifFile::new()
+            .with_transaction(t1)
+            .assert_balance(&a, Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap(), dec!(0.00))
+            .build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn balance_assertion_only_counts_transactions_up_to_its_date() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t1 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 12, 5, 0, 0, 0).unwrap())
+            .amount(dec!(50.00))
+            .build()
+            .unwrap();
+
+        let result = QifFile::new()
+            .with_transaction(t2)
+            .with_transaction(t1)
+            .assert_balance(&a, Utc.with_ymd_and_hms(2020, 11, 30, 0, 0, 0).unwrap(), dec!(-10.00))
+            .build();
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn balance_assertion_ignores_other_accounts() {
+        let checking = Account::new().name("Checking").account_type(AccountType::Bank);
+        let savings = Account::new().name("Savings").account_type(AccountType::Cash);
+
+        let t1 = Transaction::new(&checking)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-10.00))
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&savings)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(500.00))
+            .build()
+            .unwrap();
+
+        let result = QifFile::new()
+            .with_transaction(t1)
+            .with_transaction(t2)
+            .assert_balance(&checking, Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap(), dec!(-10.00))
+            .build();
+
+        assert!(result.is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use rust_decimal::Decimal;
 use std::fmt;
 
 /// Represent a Split, which is basically a portion of a transaction
@@ -5,7 +6,7 @@ use std::fmt;
 pub struct Split {
     category: String,
     memo: String,
-    pub(in crate) amount: i64,
+    pub(in crate) amount: Decimal,
 }
 
 impl Split {
@@ -23,7 +24,7 @@ impl Split {
         self
     }
 
-    pub fn amount(mut self, val: i64) -> Self {
+    pub fn amount(mut self, val: Decimal) -> Self {
         self.amount = val;
         self
     }
@@ -39,32 +40,24 @@ impl Split {
 
 impl fmt::Display for Split {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let amount_line = format!("{0:03}", self.amount);
-
-        writeln!(
-            f,
-            "S{0}\nE{1}\n${2}.{3}",
-            self.category,
-            self.memo,
-            &amount_line[..amount_line.len() - 2],
-            &amount_line[amount_line.len() - 2..]
-        )
+        writeln!(f, "S{0}\nE{1}\n${2:.2}", self.category, self.memo, self.amount)
     }
 }
 
 #[cfg(test)]
 mod split_test {
     use super::*;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn split_format() {
         let s = Split::new()
-            .amount(-1000)
+            .amount(dec!(-10.00))
             .category("testcat")
             .memo("testmemo")
             .build();
         let s2 = Split::new()
-            .amount(-1000)
+            .amount(dec!(-10.00))
             .category("testcat")
             .memo("")
             .build();
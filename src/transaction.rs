@@ -1,26 +1,69 @@
-use crate::account::Account;
+use crate::account::{Account, AccountType};
 use crate::split::Split;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use std::fmt;
 
+/// Action recorded against an `!Type:Invst` record's `N` field.
+///
+/// This is the minimal set of actions needed to record buying, selling and
+/// receiving securities; Quicken supports more (e.g. `Cash`, `XIn`/`XOut`)
+/// that can be added here as they're needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvestmentAction {
+    Buy,
+    Sell,
+    Div,
+    ReinvDiv,
+    ShrsIn,
+    ShrsOut,
+}
+
+impl fmt::Display for InvestmentAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action_string = match self {
+            InvestmentAction::Buy => "Buy",
+            InvestmentAction::Sell => "Sell",
+            InvestmentAction::Div => "Div",
+            InvestmentAction::ReinvDiv => "ReinvDiv",
+            InvestmentAction::ShrsIn => "ShrsIn",
+            InvestmentAction::ShrsOut => "ShrsOut",
+        };
+        write!(f, "{0}", action_string)
+    }
+}
+
 /// Single QIF transaction
 #[derive(Debug)]
 pub struct Transaction<'a> {
     account: &'a Account,
     /// Date of transaction, time is not supported in QIF format
     date: DateTime<Utc>,
-    /// Last two digits is cents
-    amount: i64,
+    amount: Decimal,
     payee: String,
     memo: String,
     /// Category is used when transaction is spent in single piece, otherwise
     /// `splits` is used with local categorization
     category: String,
     cleared_status: String,
+    /// Transaction number/check number, rendered as the QIF `N` line.
+    number: Option<String>,
     /// Parts of transaction used for description of different categories.
     /// `Transaction` owns this vector since all splits do only have meaning in
     /// scope of the transaction.
     splits: Vec<Split>,
+    /// The remaining fields only apply to `!Type:Invst` records and are
+    /// rendered in addition to the fields above when `account`'s type is
+    /// `AccountType::Investment`; on such records `number` is not rendered,
+    /// since their `N` line holds the action.
+    action: Option<InvestmentAction>,
+    security: Option<String>,
+    /// Price per share
+    price: Option<Decimal>,
+    /// Number of shares, which may be fractional.
+    quantity: Option<f64>,
+    /// Commission paid on the transaction
+    commission: Option<Decimal>,
 }
 
 impl<'a> Transaction<'a> {
@@ -28,12 +71,18 @@ impl<'a> Transaction<'a> {
         Transaction {
             account: acc,
             date: Utc::now(),
-            amount: 0,
+            amount: Decimal::ZERO,
             payee: String::new(),
             memo: String::new(),
             category: String::new(),
             cleared_status: String::new(),
+            number: None,
             splits: Vec::new(),
+            action: None,
+            security: None,
+            price: None,
+            quantity: None,
+            commission: None,
         }
     }
 
@@ -42,7 +91,7 @@ impl<'a> Transaction<'a> {
         self
     }
 
-    pub fn amount(mut self, val: i64) -> Self {
+    pub fn amount(mut self, val: Decimal) -> Self {
         self.amount = val;
         self
     }
@@ -67,15 +116,121 @@ impl<'a> Transaction<'a> {
         self
     }
 
+    pub fn number(mut self, val: &str) -> Self {
+        self.number = Some(String::from(val));
+        self
+    }
+
     pub fn splits(mut self, val: &[Split]) -> Self {
-        let sum = val.iter().fold(0, |acc, e| acc + e.amount);
+        let sum = val.iter().fold(Decimal::ZERO, |acc, e| acc + e.amount);
         self.amount = sum;
         self.splits = val.to_owned();
         self
     }
 
+    /// Divides `self.amount` evenly across `categories`, assigning any
+    /// rounding remainder to the last category so the splits always sum
+    /// exactly to `self.amount`.
+    pub fn split_equally(mut self, categories: &[&str]) -> Self {
+        if categories.is_empty() {
+            return self;
+        }
+
+        let share = (self.amount / Decimal::from(categories.len() as u32)).round_dp(2);
+        let mut splits: Vec<Split> = categories
+            .iter()
+            .map(|category| Split::new().category(category).amount(share).build())
+            .collect();
+
+        let last = splits.len() - 1;
+        assign_remainder(&mut splits, self.amount, last);
+        self.splits = splits;
+        self
+    }
+
+    /// Divides `self.amount` across `shares` proportionally to the given
+    /// percentages (e.g. `&[("Rent", 60.0), ("Food", 40.0)]`), assigning any
+    /// rounding remainder to the last share.
+    pub fn split_by_percent(mut self, shares: &[(&str, f64)]) -> Self {
+        if shares.is_empty() {
+            return self;
+        }
+
+        let mut splits: Vec<Split> = shares
+            .iter()
+            .map(|(category, percent)| {
+                let percent = Decimal::try_from(*percent).unwrap_or_default();
+                let amount = (self.amount * percent / Decimal::from(100)).round_dp(2);
+                Split::new().category(category).amount(amount).build()
+            })
+            .collect();
+
+        let last = splits.len() - 1;
+        assign_remainder(&mut splits, self.amount, last);
+        self.splits = splits;
+        self
+    }
+
+    /// Divides `self.amount` across `shares` proportionally to the given
+    /// integer share counts (e.g. `&[("Alice", 2), ("Bob", 1)]` splits 2:1),
+    /// assigning any rounding remainder to the largest share.
+    pub fn split_by_shares(mut self, shares: &[(&str, u32)]) -> Self {
+        if shares.is_empty() {
+            return self;
+        }
+
+        let total_shares: u32 = shares.iter().map(|(_, count)| count).sum();
+        if total_shares == 0 {
+            return self;
+        }
+
+        let mut splits: Vec<Split> = shares
+            .iter()
+            .map(|(category, count)| {
+                let amount =
+                    (self.amount * Decimal::from(*count) / Decimal::from(total_shares)).round_dp(2);
+                Split::new().category(category).amount(amount).build()
+            })
+            .collect();
+
+        let (largest, _) = shares
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, (_, count))| *count)
+            .unwrap();
+        assign_remainder(&mut splits, self.amount, largest);
+        self.splits = splits;
+        self
+    }
+
+    pub fn action(mut self, val: InvestmentAction) -> Self {
+        self.action = Some(val);
+        self
+    }
+
+    pub fn security(mut self, val: &str) -> Self {
+        self.security = Some(String::from(val));
+        self
+    }
+
+    pub fn price(mut self, val: Decimal) -> Self {
+        self.price = Some(val);
+        self
+    }
+
+    pub fn quantity(mut self, val: f64) -> Self {
+        self.quantity = Some(val);
+        self
+    }
+
+    pub fn commission(mut self, val: Decimal) -> Self {
+        self.commission = Some(val);
+        self
+    }
+
     pub fn build(self) -> Result<Transaction<'a>, String> {
-        if self.splits.iter().fold(0, |acc, e| acc + e.amount) != self.amount {
+        let splits_total = self.splits.iter().fold(Decimal::ZERO, |acc, e| acc + e.amount);
+        if !self.splits.is_empty() && splits_total != self.amount {
             Err("Sum of splits is not equal resulting amount".to_string())
         } else {
             Ok(Transaction {
@@ -86,7 +241,13 @@ impl<'a> Transaction<'a> {
                 memo: self.memo,
                 category: self.category,
                 cleared_status: self.cleared_status,
+                number: self.number,
                 splits: self.splits,
+                action: self.action,
+                security: self.security,
+                price: self.price,
+                quantity: self.quantity,
+                commission: self.commission,
             })
         }
     }
@@ -97,28 +258,93 @@ impl<'a> Transaction<'a> {
         self
     }
 
-    pub fn sum(&self) -> i64 {
+    pub fn sum(&self) -> Decimal {
         self.amount
     }
+
+    /// The account this transaction belongs to, used by `QifFile` to group
+    /// transactions under a single `!Account`/`!Type:` section.
+    pub(crate) fn account(&self) -> &'a Account {
+        self.account
+    }
+
+    /// The transaction's date, used by `QifFile` to order transactions
+    /// chronologically for balance assertions.
+    pub(crate) fn get_date(&self) -> DateTime<Utc> {
+        self.date
+    }
+
+    /// A stable MD5 digest of the transaction's date, amount, payee and
+    /// memo. Two transactions with the same content hash to the same
+    /// `content_id`, which [`dedup_by_content`] uses to drop re-imported
+    /// duplicates.
+    pub fn content_id(&self) -> String {
+        let normalized = format!(
+            "{}|{}|{}|{}",
+            self.date.format("%Y-%m-%d"),
+            self.amount,
+            self.payee.trim().to_lowercase(),
+            self.memo.trim().to_lowercase()
+        );
+        format!("{:x}", md5::compute(normalized))
+    }
+}
+
+/// Drops transactions sharing the same [`Transaction::content_id`] as an
+/// earlier one in `transactions`, keeping the first occurrence. Useful when
+/// assembling a register from overlapping CSV exports that would otherwise
+/// produce duplicate QIF entries.
+pub fn dedup_by_content(transactions: Vec<Transaction<'_>>) -> Vec<Transaction<'_>> {
+    let mut seen = std::collections::HashSet::new();
+    transactions
+        .into_iter()
+        .filter(|t| seen.insert(t.content_id()))
+        .collect()
 }
 
 impl<'a> fmt::Display for Transaction<'a> {
+    /// Writes the transaction body only (`D/P/M/L/C/T...^`), without the
+    /// `!Type:` header. The header is written by `QifFile` at the start of
+    /// each account section, since a real QIF register carries a single `!Type:` line
+    /// for a whole stream of transactions rather than one per record.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let amount_line = format!("{0:03}", self.amount);
+        writeln!(f, "D{0}", self.date.format("%m/%d/%Y"))?;
+
+        let is_investment = self.account.get_type() == AccountType::Investment;
+
+        // The `N` line means "action" on an Invst record and "transaction
+        // number" on every other record type, so only emit `self.number`
+        // when it won't collide with the investment action below.
+        if !is_investment {
+            if let Some(number) = &self.number {
+                writeln!(f, "N{0}", number)?;
+            }
+        }
 
         writeln!(
             f,
-            "!Type:{0}\nD{1}\nP{2}\nM{3}\nL{4}\nC{5}\nT{6}.{7}",
-            self.account.get_type(),
-            self.date.format("%m/%d/%Y"),
-            self.payee,
-            self.memo,
-            self.category,
-            self.cleared_status,
-            &amount_line[..amount_line.len() - 2],
-            &amount_line[amount_line.len() - 2..]
+            "P{0}\nM{1}\nL{2}\nC{3}\nT{4:.2}",
+            self.payee, self.memo, self.category, self.cleared_status, self.amount
         )?;
 
+        if is_investment {
+            if let Some(action) = self.action {
+                writeln!(f, "N{0}", action)?;
+            }
+            if let Some(security) = &self.security {
+                writeln!(f, "Y{0}", security)?;
+            }
+            if let Some(price) = self.price {
+                writeln!(f, "I{0:.2}", price)?;
+            }
+            if let Some(quantity) = self.quantity {
+                writeln!(f, "Q{0}", quantity)?;
+            }
+            if let Some(commission) = self.commission {
+                writeln!(f, "O{0:.2}", commission)?;
+            }
+        }
+
         if !self.splits.is_empty() {
             for s in self.splits.iter() {
                 write!(f, "{}", s)?;
@@ -128,11 +354,22 @@ impl<'a> fmt::Display for Transaction<'a> {
     }
 }
 
+/// Tops up the split at `idx` with whatever is left after rounding, so
+/// `splits` always sums exactly to `total`.
+fn assign_remainder(splits: &mut [Split], total: Decimal, idx: usize) {
+    let distributed = splits.iter().fold(Decimal::ZERO, |acc, s| acc + s.amount);
+    let remainder = total - distributed;
+    if remainder != Decimal::ZERO {
+        splits[idx].amount += remainder;
+    }
+}
+
 #[cfg(test)]
 mod receipt {
     use super::*;
     use crate::account::AccountType;
     use chrono::prelude::*;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn transaction_format() {
@@ -141,14 +378,13 @@ mod receipt {
             .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
             .category("testcat")
             .memo("testmemo")
-            .amount(0)
+            .amount(dec!(0.00))
             .build()
             .unwrap();
 
         assert_eq!(
             t.to_string(),
-            r#"!Type:Cash
-D11/28/2020
+            r#"D11/28/2020
 P
 Mtestmemo
 Ltestcat
@@ -163,8 +399,8 @@ T0.00
     fn split_transaction_format() {
         let a = Account::new().account_type(AccountType::Investment);
 
-        let s1 = Split::new().category("Cat1").memo("Split1").amount(-1000);
-        let s2 = Split::new().category("Cat2").memo("Split2").amount(-2000);
+        let s1 = Split::new().category("Cat1").memo("Split1").amount(dec!(-10.00));
+        let s2 = Split::new().category("Cat2").memo("Split2").amount(dec!(-20.00));
 
         let t = Transaction::new(&a)
             .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
@@ -177,8 +413,7 @@ T0.00
 
         assert_eq!(
             t.to_string(),
-            r#"!Type:Invst
-D11/28/2020
+            r#"D11/28/2020
 P
 Mtestmemo
 Ltestcat
@@ -199,8 +434,8 @@ $-20.00
     fn split_list_check() {
         let a = Account::new().account_type(AccountType::Investment);
 
-        let s1 = Split::new().category("Cat1").memo("Split1").amount(-1000);
-        let s2 = Split::new().category("Cat2").memo("Split2").amount(-2000);
+        let s1 = Split::new().category("Cat1").memo("Split1").amount(dec!(-10.00));
+        let s2 = Split::new().category("Cat2").memo("Split2").amount(dec!(-20.00));
 
         let splits = vec![s1, s2];
 
@@ -214,8 +449,7 @@ $-20.00
 
         assert_eq!(
             t.to_string(),
-            r#"!Type:Invst
-D11/28/2020
+            r#"D11/28/2020
 P
 Mtestmemo
 Ltestcat
@@ -231,4 +465,265 @@ $-20.00
 "#
         );
     }
+
+    #[test]
+    fn investment_transaction_format() {
+        let a = Account::new().account_type(AccountType::Investment);
+
+        let t = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(-100.00))
+            .action(InvestmentAction::Buy)
+            .security("ACME")
+            .price(dec!(100.00))
+            .quantity(1.0)
+            .commission(dec!(9.95))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            t.to_string(),
+            r#"D11/28/2020
+P
+M
+L
+C
+T-100.00
+NBuy
+YACME
+I100.00
+Q1
+O9.95
+^
+"#
+        );
+    }
+
+    #[test]
+    fn bank_transaction_ignores_investment_fields() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(0.00))
+            .action(InvestmentAction::Buy)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            t.to_string(),
+            r#"D11/28/2020
+P
+M
+L
+C
+T0.00
+^
+"#
+        );
+    }
+
+    #[test]
+    fn exact_split_sum_rejects_rounding_mismatch() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let s1 = Split::new().category("Cat1").amount(dec!(1.371));
+        let s2 = Split::new().category("Cat2").amount(dec!(1.371));
+
+        let result = Transaction::new(&a).with_split(&s1).with_split(&s2).build();
+
+        assert_eq!(result.unwrap().sum(), dec!(2.742));
+
+        let mismatched = Transaction::new(&a)
+            .with_split(&s1)
+            .with_split(&s2)
+            .amount(dec!(2.75))
+            .build();
+
+        assert!(mismatched.is_err());
+    }
+
+    #[test]
+    fn split_equally_assigns_remainder_to_last() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(10.00))
+            .split_equally(&["Cat1", "Cat2", "Cat3"])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            t.to_string(),
+            r#"D11/28/2020
+P
+M
+L
+C
+T10.00
+SCat1
+E
+$3.33
+SCat2
+E
+$3.33
+SCat3
+E
+$3.34
+^
+"#
+        );
+    }
+
+    #[test]
+    fn split_by_percent_sums_exactly() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .amount(dec!(100.00))
+            .split_by_percent(&[("Rent", 33.33), ("Food", 33.33), ("Fun", 33.34)])
+            .build()
+            .unwrap();
+
+        assert_eq!(t.sum(), dec!(100.00));
+        let splits_sum: Decimal = t.splits.iter().fold(Decimal::ZERO, |acc, s| acc + s.amount);
+        assert_eq!(splits_sum, dec!(100.00));
+    }
+
+    #[test]
+    fn split_by_shares_assigns_remainder_to_largest_share() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .amount(dec!(10.00))
+            .split_by_shares(&[("Alice", 1), ("Bob", 1), ("Carol", 1)])
+            .build()
+            .unwrap();
+
+        let amounts: Vec<Decimal> = t.splits.iter().map(|s| s.amount).collect();
+        assert_eq!(amounts.iter().fold(Decimal::ZERO, |acc, a| acc + a), dec!(10.00));
+        assert_eq!(amounts, vec![dec!(3.33), dec!(3.33), dec!(3.34)]);
+    }
+
+    #[test]
+    fn split_by_shares_with_all_zero_counts_leaves_splits_empty() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .amount(dec!(10.00))
+            .split_by_shares(&[("Alice", 0), ("Bob", 0)])
+            .build()
+            .unwrap();
+
+        assert!(t.splits.is_empty());
+    }
+
+    #[test]
+    fn number_renders_n_line_on_bank_transactions() {
+        let a = Account::new().account_type(AccountType::Bank);
+
+        let t = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(0.00))
+            .number("1001")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            t.to_string(),
+            r#"D11/28/2020
+N1001
+P
+M
+L
+C
+T0.00
+^
+"#
+        );
+    }
+
+    #[test]
+    fn investment_transaction_ignores_number() {
+        let a = Account::new().account_type(AccountType::Investment);
+
+        let t = Transaction::new(&a)
+            .date(Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap())
+            .amount(dec!(0.00))
+            .number("1001")
+            .action(InvestmentAction::Buy)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            t.to_string(),
+            r#"D11/28/2020
+P
+M
+L
+C
+T0.00
+NBuy
+^
+"#
+        );
+    }
+
+    #[test]
+    fn content_id_is_stable_and_sensitive_to_content() {
+        let a = Account::new().account_type(AccountType::Bank);
+        let date = Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap();
+
+        let t1 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-10.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-10.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+        let t3 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-20.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+
+        assert_eq!(t1.content_id(), t2.content_id());
+        assert_ne!(t1.content_id(), t3.content_id());
+    }
+
+    #[test]
+    fn dedup_by_content_drops_repeats() {
+        let a = Account::new().account_type(AccountType::Bank);
+        let date = Utc.with_ymd_and_hms(2020, 11, 28, 0, 0, 0).unwrap();
+
+        let t1 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-10.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+        let t2 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-10.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+        let t3 = Transaction::new(&a)
+            .date(date)
+            .amount(dec!(-20.00))
+            .payee("Shop")
+            .build()
+            .unwrap();
+
+        let deduped = dedup_by_content(vec![t1, t2, t3]);
+
+        assert_eq!(deduped.len(), 2);
+    }
 }